@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet};
 use wasm_bindgen::prelude::*;
 use quizx::graph::*;
 use quizx::vec_graph::Graph;
@@ -5,6 +6,10 @@ use quizx::basic_rules::*;
 use quizx::simplify::*;
 use serde::{Serialize, Deserialize};
 
+mod pattern;
+mod qasm;
+mod serialize;
+
 // Set up panic hook for better error messages in the browser.
 #[wasm_bindgen(start)]
 fn init_wasm() {
@@ -65,10 +70,151 @@ pub struct EdgeInfo {
     edge_type: u8, // 0 = Simple (N), 1 = Hadamard (H)
 }
 
+/// A single recorded rule application, detailed enough to invert or replay it
+/// without re-running the simplifier.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RewriteStep {
+    rule: String,
+    removed_vertices: Vec<VertexInfo>,
+    removed_edges: Vec<EdgeInfo>,
+    added_vertices: Vec<VertexInfo>,
+    added_edges: Vec<EdgeInfo>,
+}
+
+/// Which family of built-in rules `begin_simplify` should drive.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SimplifyStrategy {
+    Clifford,
+    Full,
+}
+
+impl SimplifyStrategy {
+    fn from_u8(strategy: u8) -> Self {
+        match strategy {
+            0 => SimplifyStrategy::Clifford,
+            _ => SimplifyStrategy::Full,
+        }
+    }
+
+    /// Today both strategies drive the same rule set: every rewrite this
+    /// driver knows about is Clifford-safe. This stays a separate branch as
+    /// a seam for when `Full` picks up rules (e.g. non-Clifford phase-gadget
+    /// fusion) that `Clifford` should skip.
+    fn rule_cycle(self) -> &'static [RuleClass] {
+        match self {
+            SimplifyStrategy::Clifford => &RuleClass::ORDER,
+            SimplifyStrategy::Full => &RuleClass::ORDER,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RuleClass {
+    SpiderFusion,
+    IdentityRemoval,
+    LocalComplementation,
+    Pivot,
+}
+
+impl RuleClass {
+    const ORDER: [RuleClass; 4] = [
+        RuleClass::SpiderFusion,
+        RuleClass::IdentityRemoval,
+        RuleClass::LocalComplementation,
+        RuleClass::Pivot,
+    ];
+}
+
+/// Steppable-simplification state: which strategy is running, which rule
+/// class `step` will try next, and how far the reduction has gotten.
+struct SimplifyDriver {
+    strategy: SimplifyStrategy,
+    cursor: usize,
+    rules_applied: u64,
+    done: bool,
+}
+
+#[derive(Serialize)]
+struct SimplifyStatus {
+    done: bool,
+    rules_applied: u64,
+    vertices: usize,
+    edges: usize,
+}
+
+pub(crate) fn vertex_type_from_u8(vertex_type: u8) -> VType {
+    match vertex_type {
+        0 => VType::B,
+        1 => VType::Z,
+        2 => VType::X,
+        3 => VType::H,
+        _ => VType::Z,
+    }
+}
+
+pub(crate) fn edge_type_from_u8(edge_type: u8) -> EType {
+    match edge_type {
+        1 => EType::H,
+        _ => EType::N,
+    }
+}
+
+fn edge_type_to_u8(edge_type: EType) -> u8 {
+    match edge_type {
+        EType::N => 0,
+        EType::H => 1,
+        EType::Wio => 0, // treat Wio same as simple for now
+    }
+}
+
+/// Parse a phase string in the "num" or "num/denom" format used throughout
+/// this module into a rational multiple of π.
+pub(crate) fn parse_phase_str(phase_str: &str) -> Result<num::rational::Rational64, String> {
+    use num::rational::Rational64;
+
+    let parts: Vec<&str> = phase_str.trim().split('/').collect();
+    match parts.len() {
+        1 => {
+            let num = parts[0].parse::<i64>()
+                .map_err(|e| format!("Invalid numerator: {}", e))?;
+            Ok(Rational64::new(num, 1))
+        }
+        2 => {
+            let num = parts[0].parse::<i64>()
+                .map_err(|e| format!("Invalid numerator: {}", e))?;
+            let denom = parts[1].parse::<i64>()
+                .map_err(|e| format!("Invalid denominator: {}", e))?;
+            if denom == 0 {
+                return Err("Invalid denominator: must not be zero".to_string());
+            }
+            Ok(Rational64::new(num, denom))
+        }
+        _ => Err("Phase must be in format 'num' or 'num/denom'".to_string())
+    }
+}
+
+fn edges_match(a: &EdgeInfo, b: &EdgeInfo) -> bool {
+    a.edge_type == b.edge_type &&
+        ((a.source == b.source && a.target == b.target) ||
+         (a.source == b.target && a.target == b.source))
+}
+
+fn edge_info_list(g: &Graph) -> Vec<EdgeInfo> {
+    g.edges()
+        .map(|(s, t, et)| EdgeInfo { source: s, target: t, edge_type: edge_type_to_u8(et) })
+        .collect()
+}
+
 /// Main ZX-diagram graph wrapper for WASM
 #[wasm_bindgen]
 pub struct ZXGraph {
     inner: Graph,
+    tracing: bool,
+    trace: Vec<RewriteStep>,
+    redo_stack: Vec<RewriteStep>,
+    last_pattern: Option<pattern::Pattern>,
+    last_matches: Vec<HashMap<usize, usize>>,
+    driver: Option<SimplifyDriver>,
 }
 
 #[wasm_bindgen]
@@ -77,6 +223,57 @@ impl ZXGraph {
     pub fn new() -> ZXGraph {
         ZXGraph {
             inner: Graph::new(),
+            tracing: false,
+            trace: Vec::new(),
+            redo_stack: Vec::new(),
+            last_pattern: None,
+            last_matches: Vec::new(),
+            driver: None,
+        }
+    }
+
+    /// Turn on step recording. Once enabled, the single-rule `apply_*`
+    /// helpers and the bulk `simplify_full`/`simplify_clifford` passes push a
+    /// `RewriteStep` per rule application instead of mutating `inner` blind.
+    pub fn enable_tracing(&mut self) {
+        self.tracing = true;
+    }
+
+    /// The recorded steps so far, oldest first, as a JSON array.
+    pub fn get_trace_json(&self) -> String {
+        serde_json::to_string(&self.trace).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Undo the most recent step, reversing its delta against `inner`.
+    /// Returns false if there is nothing to undo.
+    pub fn undo_last_step(&mut self) -> bool {
+        match self.trace.pop() {
+            Some(step) => {
+                // `invert_step` returns `step` with its `removed_*` ids
+                // corrected to whatever ids the reinserted vertices actually
+                // got; `redo` must replay that corrected copy, not the
+                // original, or it will operate on stale ids if quizx didn't
+                // hand the reinsertion back its original indices.
+                let inverted = self.invert_step(&step);
+                self.redo_stack.push(inverted);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-apply the most recently undone step. Returns false if there is
+    /// nothing to redo, or if a new step has been recorded since the undo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(step) => {
+                // Same id-correction concern as `undo_last_step`, mirrored
+                // for `added_*`.
+                let replayed = self.replay_step(&step);
+                self.trace.push(replayed);
+                true
+            }
+            None => false,
         }
     }
 
@@ -154,27 +351,7 @@ impl ZXGraph {
     /// Set the phase of a vertex (as a rational multiple of π).
     /// For example: phase_str = "1/2" means π/2, "1" means π, "0" means 0.
     pub fn set_vertex_phase(&mut self, vertex: usize, phase_str: &str) -> Result<(), String> {
-        use num::rational::Rational64;
-
-        let parts: Vec<&str> = phase_str.trim().split('/').collect();
-        let phase = match parts.len() {
-            1 => {
-                // Just a numerator (e.g., "0", "1", "-1")
-                let num = parts[0].parse::<i64>()
-                    .map_err(|e| format!("Invalid numerator: {}", e))?;
-                Rational64::new(num, 1)
-            }
-            2 => {
-                // Numerator and denominator (e.g., "1/2", "3/4")
-                let num = parts[0].parse::<i64>()
-                    .map_err(|e| format!("Invalid numerator: {}", e))?;
-                let denom = parts[1].parse::<i64>()
-                    .map_err(|e| format!("Invalid denominator: {}", e))?;
-                Rational64::new(num, denom)
-            }
-            _ => return Err("Phase must be in format 'num' or 'num/denom'".to_string())
-        };
-
+        let phase = parse_phase_str(phase_str)?;
         self.inner.set_phase(vertex, phase);
         Ok(())
     }
@@ -203,16 +380,7 @@ impl ZXGraph {
 
     pub fn get_vertices_json(&self) -> String {
         let vertices: Vec<VertexInfo> = self.inner.vertices()
-            .map(|v| {
-                let vt: VertexType = self.inner.vertex_type(v).into();
-                VertexInfo {
-                    id: v,
-                    vertex_type: vt as u8,
-                    phase: format!("{}", self.inner.phase(v)),
-                    row: self.inner.row(v),
-                    col: self.inner.qubit(v),
-                }
-            })
+            .map(|v| self.vertex_info(v))
             .collect();
 
         serde_json::to_string(&vertices).unwrap_or_else(|_| "[]".to_string())
@@ -235,13 +403,15 @@ impl ZXGraph {
     }
 
     pub fn apply_spider_fusion(&mut self) -> bool {
-        match self.inner.find_edge(|v0, v1, _| check_spider_fusion(&self.inner, v0, v1)) {
-            Some((v0, v1, _)) => {
-                spider_fusion_unchecked(&mut self.inner, v0, v1);
-                true
+        self.record_step("spider_fusion", |g| {
+            match g.find_edge(|v0, v1, _| check_spider_fusion(g, v0, v1)) {
+                Some((v0, v1, _)) => {
+                    spider_fusion_unchecked(g, v0, v1);
+                    true
+                }
+                None => false,
             }
-            None => false,
-        }
+        })
     }
 
     pub fn full_spider_fusion(&mut self) -> usize {
@@ -252,88 +422,238 @@ impl ZXGraph {
         count
     }
 
+    /// Remove one identity spider (phase 0, degree 2), if any remain.
+    pub fn apply_identity_removal(&mut self) -> bool {
+        self.record_step("identity_removal", |g| {
+            match g.vertices().find(|&v| check_remove_id(g, v)) {
+                Some(v) => {
+                    remove_id_unchecked(g, v);
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    pub fn full_identity_removal(&mut self) -> usize {
+        let mut count = 0;
+        while self.apply_identity_removal() {
+            count += 1;
+        }
+        count
+    }
+
     /// Remove identity spiders (phase 0, degree 2).
     pub fn simplify_identities(&mut self) -> bool {
-        id_simp(&mut self.inner)
+        self.record_step("identity_removal", |g| id_simp(g))
+    }
+
+    /// Apply one local complementation, if a vertex it applies to remains.
+    pub fn apply_local_comp(&mut self) -> bool {
+        self.record_step("local_complementation", |g| {
+            match g.vertices().find(|&v| check_local_comp(g, v)) {
+                Some(v) => {
+                    local_comp_unchecked(g, v);
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    pub fn full_local_comp(&mut self) -> usize {
+        let mut count = 0;
+        while self.apply_local_comp() {
+            count += 1;
+        }
+        count
     }
 
     pub fn simplify_local_comp(&mut self) -> bool {
-        local_comp_simp(&mut self.inner)
+        self.record_step("local_complementation", |g| local_comp_simp(g))
     }
 
     pub fn simplify_spiders(&mut self) -> bool {
-        spider_simp(&mut self.inner)
+        self.record_step("spider_fusion", |g| spider_simp(g))
+    }
+
+    /// Apply one pivot, if an edge it applies to remains.
+    pub fn apply_pivot(&mut self) -> bool {
+        self.record_step("pivot", |g| {
+            match g.find_edge(|v0, v1, _| check_pivot(g, v0, v1)) {
+                Some((v0, v1, _)) => {
+                    pivot_unchecked(g, v0, v1);
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    pub fn full_pivot(&mut self) -> usize {
+        let mut count = 0;
+        while self.apply_pivot() {
+            count += 1;
+        }
+        count
     }
 
     pub fn simplify_pivots(&mut self) -> bool {
-        pivot_simp(&mut self.inner)
+        self.record_step("pivot", |g| pivot_simp(g))
     }
 
     /// Apply Clifford simplification (combines multiple rules).
+    ///
+    /// While tracing, this drives the same rule set one application at a
+    /// time so the trace has one step per rewrite rather than one aggregate
+    /// step for the whole pass.
     pub fn simplify_clifford(&mut self) -> bool {
-        clifford_simp(&mut self.inner)
+        if !self.tracing {
+            return clifford_simp(&mut self.inner);
+        }
+        self.simplify_step_by_step()
     }
 
     /// Apply full simplification (all rules until fully reduced).
+    ///
+    /// See `simplify_clifford` for why tracing takes a different path. That
+    /// path applies only spider fusion, identity removal, local
+    /// complementation and pivoting; `full_simp`'s other rules are not run
+    /// while tracing.
     pub fn simplify_full(&mut self) -> bool {
-        full_simp(&mut self.inner)
+        if !self.tracing {
+            return full_simp(&mut self.inner);
+        }
+        self.simplify_step_by_step()
     }
 
-    pub fn to_json(&self) -> String {
-        let vertices_json = self.get_vertices_json();
-        let edges_json = self.get_edges_json();
-
-        format!(r#"{{"vertices":{},"edges":{}}}"#, vertices_json, edges_json)
+    /// Set up a steppable reduction (strategy: 0=Clifford, 1=Full) that
+    /// `step` advances a bounded number of rewrites at a time, so a Web
+    /// Worker loop can drive it, report progress, and stay responsive
+    /// instead of blocking on `simplify_full`/`simplify_clifford`.
+    pub fn begin_simplify(&mut self, strategy: u8) {
+        self.driver = Some(SimplifyDriver {
+            strategy: SimplifyStrategy::from_u8(strategy),
+            cursor: 0,
+            rules_applied: 0,
+            done: false,
+        });
     }
 
-    pub fn from_json(json: &str) -> Result<ZXGraph, String> {
-        use serde_json::Value;
+    /// Apply up to `max_rules` rewrites from the in-progress `begin_simplify`
+    /// reduction, one rewrite per rule class per try (see `apply_rule_class`)
+    /// so `max_rules` genuinely bounds the work done, not just how many bulk
+    /// passes run. Returns a JSON `{done, rules_applied, vertices, edges}`
+    /// status; `done` is also true if no reduction is in progress. The
+    /// graph is always left in a consistent state when this returns, so the
+    /// frontend can render it between calls.
+    pub fn step(&mut self, max_rules: u32) -> String {
+        let mut applied_this_call = 0u32;
 
-        let v: Value = serde_json::from_str(json)
-            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+        loop {
+            if applied_this_call >= max_rules {
+                break;
+            }
+            match &self.driver {
+                Some(d) if !d.done => {}
+                _ => break,
+            }
 
-        let mut g = Graph::new();
+            let strategy = self.driver.as_ref().unwrap().strategy;
+            let cycle = strategy.rule_cycle();
+            let cursor = self.driver.as_ref().unwrap().cursor;
+            let rule = cycle[cursor % cycle.len()];
 
-        if let Some(vertices) = v["vertices"].as_array() {
-            for vertex_val in vertices {
-                let vertex_type = vertex_val["vertex_type"].as_u64()
-                    .ok_or("Missing vertex_type")? as u8;
-                let row = vertex_val["row"].as_f64().ok_or("Missing row")?;
-                let col = vertex_val["col"].as_f64().ok_or("Missing col")?;
-
-                let vt = match vertex_type {
-                    0 => VType::B,
-                    1 => VType::Z,
-                    2 => VType::X,
-                    3 => VType::H,
-                    _ => VType::Z,
-                };
-
-                let v = g.add_vertex(vt);
-                g.set_row(v, row);
-                g.set_qubit(v, col);
-
-                // TODO: Handle phase if needed.
+            if self.apply_rule_class(rule) {
+                let driver = self.driver.as_mut().unwrap();
+                driver.rules_applied += 1;
+                driver.cursor = 0;
+                applied_this_call += 1;
+            } else {
+                let driver = self.driver.as_mut().unwrap();
+                driver.cursor += 1;
+                if driver.cursor >= cycle.len() {
+                    // A full lap with no rule applying means we've reached a
+                    // fixed point.
+                    driver.done = true;
+                }
             }
         }
 
-        if let Some(edges) = v["edges"].as_array() {
-            for edge_val in edges {
-                let source = edge_val["source"].as_u64().ok_or("Missing source")? as usize;
-                let target = edge_val["target"].as_u64().ok_or("Missing target")? as usize;
-                let edge_type = edge_val["edge_type"].as_u64()
-                    .ok_or("Missing edge_type")? as u8;
+        self.simplify_status_json()
+    }
+
+    /// Abort the in-progress reduction. The graph itself is untouched since
+    /// `step` never leaves it mid-rewrite; `begin_simplify` can be called
+    /// again afterwards to restart.
+    pub fn cancel(&mut self) {
+        self.driver = None;
+    }
+
+    /// Find all embeddings of a user-supplied pattern graph into `inner`.
+    /// Returns a JSON array of `{match_id, mapping}`, where `mapping` sends
+    /// pattern vertex ids to the host vertex ids they matched. The pattern
+    /// is remembered so a later `apply_rewrite_json` call can refer to a
+    /// match by id.
+    pub fn find_matches_json(&mut self, pattern_json: &str) -> Result<String, String> {
+        let pat: pattern::Pattern = serde_json::from_str(pattern_json)
+            .map_err(|e| format!("Failed to parse pattern JSON: {}", e))?;
+
+        let matches = pattern::find_matches(&self.inner, &pat);
+        let results: Vec<pattern::PatternMatch> = matches.iter().cloned().enumerate()
+            .map(|(match_id, mapping)| pattern::PatternMatch { match_id, mapping })
+            .collect();
+
+        self.last_pattern = Some(pat);
+        self.last_matches = matches;
+
+        serde_json::to_string(&results).map_err(|e| format!("Failed to serialize matches: {}", e))
+    }
 
-                let et = match edge_type {
-                    1 => EType::H,
-                    _ => EType::N,
-                };
+    /// Splice `replacement_json` in at the match `match_id` found by the
+    /// most recent `find_matches_json` call.
+    pub fn apply_rewrite_json(&mut self, match_id: usize, replacement_json: &str) -> Result<(), String> {
+        let replacement: pattern::Pattern = serde_json::from_str(replacement_json)
+            .map_err(|e| format!("Failed to parse replacement JSON: {}", e))?;
+        let pat = self.last_pattern.clone()
+            .ok_or("No pattern has been matched yet; call find_matches_json first")?;
+        let mapping = self.last_matches.get(match_id)
+            .ok_or_else(|| format!("No match with id {}", match_id))?
+            .clone();
 
-                g.add_edge_with_type(source, target, et);
+        let mut rewrite_result = Ok(());
+        self.record_step("custom_rewrite", |g| {
+            match pattern::apply_rewrite(g, &pat, &replacement, &mapping) {
+                Ok(()) => true,
+                Err(e) => {
+                    rewrite_result = Err(e);
+                    false
+                }
             }
-        }
+        });
+        rewrite_result
+    }
 
-        Ok(ZXGraph { inner: g })
+    /// Serialize the diagram to a versioned, PyZX-compatible JSON document
+    /// (`wire_vertices`/`node_vertices`/`undirected_edges`). See `serialize`
+    /// for the layout and the two pieces (the scalar encoding and `Wio`
+    /// edges) that have no PyZX counterpart.
+    pub fn to_json(&self) -> String {
+        serialize::to_json(&self.inner)
+    }
+
+    /// Parse a document produced by `to_json`.
+    pub fn from_json(json: &str) -> Result<ZXGraph, String> {
+        let inner = serialize::from_json(json).map_err(|e| e.to_string())?;
+        Ok(ZXGraph {
+            inner,
+            tracing: false,
+            trace: Vec::new(),
+            redo_stack: Vec::new(),
+            last_pattern: None,
+            last_matches: Vec::new(),
+            driver: None,
+        })
     }
 
     pub fn to_string(&self) -> String {
@@ -341,4 +661,392 @@ impl ZXGraph {
                 self.num_vertices(),
                 self.num_edges())
     }
+
+    /// Parse a practical OpenQASM 2.0 subset (qreg/creg declarations and the
+    /// gates h, x, z, rx, rz, cx, cz, s, sdg, t, tdg) into a ZX-diagram.
+    pub fn from_qasm(src: &str) -> Result<ZXGraph, String> {
+        let inner = qasm::parse(src)?;
+        Ok(ZXGraph {
+            inner,
+            tracing: false,
+            trace: Vec::new(),
+            redo_stack: Vec::new(),
+            last_pattern: None,
+            last_matches: Vec::new(),
+            driver: None,
+        })
+    }
+
+    /// Extract a circuit from the (ideally already-simplified) diagram and
+    /// render it as OpenQASM text. Fails if the diagram isn't in an
+    /// extractable (graph-like) form.
+    pub fn to_qasm(&self) -> Result<String, String> {
+        qasm::export(&self.inner)
+    }
+}
+
+/// Trace bookkeeping. Kept out of the `#[wasm_bindgen]` impl since none of
+/// this is meant to be called from JS directly.
+impl ZXGraph {
+    fn vertex_info(&self, v: usize) -> VertexInfo {
+        let vt: VertexType = self.inner.vertex_type(v).into();
+        VertexInfo {
+            id: v,
+            vertex_type: vt as u8,
+            phase: format!("{}", self.inner.phase(v)),
+            row: self.inner.row(v),
+            col: self.inner.qubit(v),
+        }
+    }
+
+    fn add_vertex_from_info(&mut self, info: &VertexInfo) -> usize {
+        let v = self.inner.add_vertex(vertex_type_from_u8(info.vertex_type));
+        self.inner.set_row(v, info.row);
+        self.inner.set_qubit(v, info.col);
+        if let Ok(phase) = parse_phase_str(&info.phase) {
+            self.inner.set_phase(v, phase);
+        }
+        v
+    }
+
+    /// Run `f` against `inner`, and when tracing is on, diff the graph
+    /// before/after and push a `RewriteStep` whenever `f` reports that it
+    /// applied.
+    fn record_step<F>(&mut self, rule: &str, f: F) -> bool
+    where
+        F: FnOnce(&mut Graph) -> bool,
+    {
+        if !self.tracing {
+            return f(&mut self.inner);
+        }
+
+        let before_vertices: Vec<VertexInfo> = self.inner.vertices()
+            .map(|v| self.vertex_info(v))
+            .collect();
+        let before_edges = edge_info_list(&self.inner);
+
+        if !f(&mut self.inner) {
+            return false;
+        }
+
+        let before_ids: HashSet<usize> = before_vertices.iter().map(|vi| vi.id).collect();
+        let after_ids: HashSet<usize> = self.inner.vertices().collect();
+
+        let removed_vertices: Vec<VertexInfo> = before_vertices.into_iter()
+            .filter(|vi| !after_ids.contains(&vi.id))
+            .collect();
+        let added_vertices: Vec<VertexInfo> = after_ids.difference(&before_ids)
+            .map(|&v| self.vertex_info(v))
+            .collect();
+
+        let after_edges = edge_info_list(&self.inner);
+        let removed_edges: Vec<EdgeInfo> = before_edges.iter()
+            .filter(|e| !after_edges.iter().any(|a| edges_match(a, e)))
+            .cloned()
+            .collect();
+        let added_edges: Vec<EdgeInfo> = after_edges.iter()
+            .filter(|e| !before_edges.iter().any(|b| edges_match(b, e)))
+            .cloned()
+            .collect();
+
+        self.trace.push(RewriteStep {
+            rule: rule.to_string(),
+            removed_vertices,
+            removed_edges,
+            added_vertices,
+            added_edges,
+        });
+        self.redo_stack.clear();
+        true
+    }
+
+    /// Drive spider fusion, identity removal, local complementation and
+    /// pivoting one rewrite at a time, until none applies, so tracing gets
+    /// one step per rule application instead of one aggregate step for the
+    /// whole pass. The other rules of `full_simp`/`clifford_simp` are not
+    /// applied here.
+    fn simplify_step_by_step(&mut self) -> bool {
+        let mut changed = false;
+        loop {
+            let applied = RuleClass::ORDER.iter().any(|&rule| self.apply_rule_class(rule));
+            if !applied {
+                break;
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Undo a recorded step: delete what it added, then reinsert what it
+    /// deleted. quizx's graph reuses freed vertex indices where it can; when
+    /// it can't, the freshly reinserted ids are remapped for this step's own
+    /// edges. Returns `step` with `removed_vertices`/`removed_edges`
+    /// corrected to the ids actually used, so the caller can hand the result
+    /// to `replay_step` later without it operating on stale ids.
+    fn invert_step(&mut self, step: &RewriteStep) -> RewriteStep {
+        for e in &step.added_edges {
+            self.inner.remove_edge(e.source, e.target);
+        }
+        for v in &step.added_vertices {
+            self.inner.remove_vertex(v.id);
+        }
+
+        let mut remap: HashMap<usize, usize> = HashMap::new();
+        let removed_vertices: Vec<VertexInfo> = step.removed_vertices.iter()
+            .map(|v| {
+                let new_id = self.add_vertex_from_info(v);
+                if new_id != v.id {
+                    remap.insert(v.id, new_id);
+                }
+                VertexInfo { id: new_id, ..v.clone() }
+            })
+            .collect();
+        let removed_edges: Vec<EdgeInfo> = step.removed_edges.iter()
+            .map(|e| EdgeInfo {
+                source: remap.get(&e.source).copied().unwrap_or(e.source),
+                target: remap.get(&e.target).copied().unwrap_or(e.target),
+                edge_type: e.edge_type,
+            })
+            .collect();
+        for e in &removed_edges {
+            self.inner.add_edge_with_type(e.source, e.target, edge_type_from_u8(e.edge_type));
+        }
+
+        RewriteStep {
+            rule: step.rule.clone(),
+            removed_vertices,
+            removed_edges,
+            added_vertices: step.added_vertices.clone(),
+            added_edges: step.added_edges.clone(),
+        }
+    }
+
+    /// Re-apply a previously undone step, mirroring `invert_step`: returns
+    /// `step` with `added_vertices`/`added_edges` corrected to whatever ids
+    /// this replay actually produced.
+    fn replay_step(&mut self, step: &RewriteStep) -> RewriteStep {
+        for e in &step.removed_edges {
+            self.inner.remove_edge(e.source, e.target);
+        }
+        for v in &step.removed_vertices {
+            self.inner.remove_vertex(v.id);
+        }
+
+        let mut remap: HashMap<usize, usize> = HashMap::new();
+        let added_vertices: Vec<VertexInfo> = step.added_vertices.iter()
+            .map(|v| {
+                let new_id = self.add_vertex_from_info(v);
+                if new_id != v.id {
+                    remap.insert(v.id, new_id);
+                }
+                VertexInfo { id: new_id, ..v.clone() }
+            })
+            .collect();
+        let added_edges: Vec<EdgeInfo> = step.added_edges.iter()
+            .map(|e| EdgeInfo {
+                source: remap.get(&e.source).copied().unwrap_or(e.source),
+                target: remap.get(&e.target).copied().unwrap_or(e.target),
+                edge_type: e.edge_type,
+            })
+            .collect();
+        for e in &added_edges {
+            self.inner.add_edge_with_type(e.source, e.target, edge_type_from_u8(e.edge_type));
+        }
+
+        RewriteStep {
+            rule: step.rule.clone(),
+            removed_vertices: step.removed_vertices.clone(),
+            removed_edges: step.removed_edges.clone(),
+            added_vertices,
+            added_edges,
+        }
+    }
+
+    /// Try to apply one rewrite of the given class; reused by `step` and
+    /// `simplify_step_by_step` so the trace and the stepping driver always
+    /// advance one rule at a time in the same way.
+    fn apply_rule_class(&mut self, rule: RuleClass) -> bool {
+        match rule {
+            RuleClass::SpiderFusion => self.apply_spider_fusion(),
+            RuleClass::IdentityRemoval => self.apply_identity_removal(),
+            RuleClass::LocalComplementation => self.apply_local_comp(),
+            RuleClass::Pivot => self.apply_pivot(),
+        }
+    }
+
+    fn simplify_status_json(&self) -> String {
+        let (done, rules_applied) = match &self.driver {
+            Some(d) => (d.done, d.rules_applied),
+            None => (true, 0),
+        };
+        let status = SimplifyStatus {
+            done,
+            rules_applied,
+            vertices: self.num_vertices(),
+            edges: self.num_edges(),
+        };
+        serde_json::to_string(&status).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two disjoint `boundary -- identity(Z, phase 0) -- boundary` wires.
+    fn identity_chain() -> Graph {
+        let mut g = Graph::new();
+        let b0 = g.add_vertex(VType::B);
+        let id0 = g.add_vertex(VType::Z);
+        let b1 = g.add_vertex(VType::B);
+        g.add_edge_with_type(b0, id0, EType::N);
+        g.add_edge_with_type(id0, b1, EType::N);
+
+        let b2 = g.add_vertex(VType::B);
+        let id1 = g.add_vertex(VType::Z);
+        let b3 = g.add_vertex(VType::B);
+        g.add_edge_with_type(b2, id1, EType::N);
+        g.add_edge_with_type(id1, b3, EType::N);
+        g
+    }
+
+    #[test]
+    fn apply_identity_removal_does_one_rewrite_per_call() {
+        let mut zx = ZXGraph { inner: identity_chain(), ..ZXGraph::new() };
+        assert_eq!(zx.num_vertices(), 6);
+
+        assert!(zx.apply_identity_removal());
+        assert_eq!(zx.num_vertices(), 5);
+
+        assert!(zx.apply_identity_removal());
+        assert_eq!(zx.num_vertices(), 4);
+
+        assert!(!zx.apply_identity_removal());
+    }
+
+    #[test]
+    fn step_applies_at_most_max_rules_rewrites() {
+        let mut zx = ZXGraph { inner: identity_chain(), ..ZXGraph::new() };
+        zx.begin_simplify(0);
+        let status_json = zx.step(1);
+        let status: serde_json::Value = serde_json::from_str(&status_json).unwrap();
+        assert_eq!(status["rules_applied"], 1);
+        assert_eq!(status["done"], false);
+        assert_eq!(zx.num_vertices(), 5);
+    }
+
+    #[test]
+    fn cancel_leaves_graph_untouched_and_resets_the_driver() {
+        let mut zx = ZXGraph { inner: identity_chain(), ..ZXGraph::new() };
+        zx.begin_simplify(0);
+        zx.step(1);
+        zx.cancel();
+        assert_eq!(zx.num_vertices(), 5);
+        // With no reduction in progress, `step` is a no-op that reports done.
+        let status_json = zx.step(1);
+        let status: serde_json::Value = serde_json::from_str(&status_json).unwrap();
+        assert_eq!(status["done"], true);
+        assert_eq!(status["rules_applied"], 0);
+        assert_eq!(zx.num_vertices(), 5);
+    }
+
+    #[test]
+    fn step_to_completion_applies_exactly_one_rewrite_per_call() {
+        let mut zx = ZXGraph { inner: identity_chain(), ..ZXGraph::new() };
+        zx.begin_simplify(0);
+
+        let mut total_applied = 0u64;
+        let mut calls = 0;
+        loop {
+            let status_json = zx.step(1);
+            let status: serde_json::Value = serde_json::from_str(&status_json).unwrap();
+            calls += 1;
+            let applied = status["rules_applied"].as_u64().unwrap();
+            assert!(applied - total_applied <= 1, "step(1) applied more than one rewrite in a call");
+            total_applied = applied;
+            if status["done"].as_bool().unwrap() {
+                break;
+            }
+            assert!(calls <= 10, "reduction did not converge in a reasonable number of steps");
+        }
+
+        assert_eq!(total_applied, 2);
+        assert_eq!(zx.num_vertices(), 4);
+    }
+
+    #[test]
+    fn undo_and_redo_restore_tracing_state() {
+        let mut zx = ZXGraph::new();
+        zx.enable_tracing();
+        let b0 = zx.add_vertex(0);
+        let id0 = zx.add_vertex(1);
+        let b1 = zx.add_vertex(0);
+        zx.add_edge(b0, id0);
+        zx.add_edge(id0, b1);
+
+        assert!(zx.apply_identity_removal());
+        assert_eq!(zx.num_vertices(), 2);
+
+        assert!(zx.undo_last_step());
+        assert_eq!(zx.num_vertices(), 3);
+
+        assert!(zx.redo());
+        assert_eq!(zx.num_vertices(), 2);
+    }
+
+    /// A custom rewrite that removes two interior vertices and adds two
+    /// fresh ones in a single step, then checks vertex and edge counts and
+    /// the boundary degrees across undo, redo and a second undo. Drives
+    /// `record_step` directly instead of depending on a
+    /// `basic_rules` precondition happening to match a hand-built graph.
+    #[test]
+    fn undo_then_redo_restores_a_two_vertex_rewrite() {
+        let mut zx = ZXGraph::new();
+        zx.enable_tracing();
+        let b0 = zx.add_vertex(0);
+        let z1 = zx.add_vertex(1);
+        let z2 = zx.add_vertex(1);
+        let b1 = zx.add_vertex(0);
+        zx.add_edge(b0, z1);
+        zx.add_edge(z1, z2);
+        zx.add_edge(z2, b1);
+
+        let before_vertices = zx.num_vertices();
+        let before_edges = zx.num_edges();
+
+        let applied = zx.record_step("test_two_vertex_rewrite", |g| {
+            g.remove_vertex(z1);
+            g.remove_vertex(z2);
+            let nz1 = g.add_vertex(VType::X);
+            let nz2 = g.add_vertex(VType::X);
+            g.add_edge_with_type(b0, nz1, EType::N);
+            g.add_edge_with_type(nz1, nz2, EType::N);
+            g.add_edge_with_type(nz2, b1, EType::N);
+            true
+        });
+        assert!(applied);
+        assert_eq!(zx.num_vertices(), before_vertices);
+        let after_rewrite_edges = zx.num_edges();
+
+        let b0_degree = |zx: &ZXGraph| zx.inner.edges().filter(|(s, t, _)| *s == b0 || *t == b0).count();
+        let b1_degree = |zx: &ZXGraph| zx.inner.edges().filter(|(s, t, _)| *s == b1 || *t == b1).count();
+
+        assert!(zx.undo_last_step());
+        assert_eq!(zx.num_vertices(), before_vertices);
+        assert_eq!(zx.num_edges(), before_edges);
+        assert_eq!(b0_degree(&zx), 1);
+        assert_eq!(b1_degree(&zx), 1);
+
+        assert!(zx.redo());
+        assert_eq!(zx.num_vertices(), before_vertices);
+        assert_eq!(zx.num_edges(), after_rewrite_edges);
+        assert_eq!(b0_degree(&zx), 1);
+        assert_eq!(b1_degree(&zx), 1);
+
+        assert!(zx.undo_last_step());
+        assert_eq!(zx.num_vertices(), before_vertices);
+        assert_eq!(zx.num_edges(), before_edges);
+        assert_eq!(b0_degree(&zx), 1);
+        assert_eq!(b1_degree(&zx), 1);
+    }
 }
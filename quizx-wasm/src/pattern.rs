@@ -0,0 +1,409 @@
+//! User-defined subgraph pattern matching and custom rewrite rules.
+//!
+//! A `Pattern` is a small graph whose vertices carry optional constraints
+//! (an exact vertex type, an exact phase, or "any") and whose edges carry a
+//! required `EType`. Non-boundary ("interior") pattern vertices must match a
+//! host vertex of exactly the same degree, so a match is "closed": nothing
+//! outside the pattern touches its interior except through a boundary
+//! vertex, which is allowed extra neighbors since it's the glue connecting
+//! the match to the rest of the diagram.
+
+use std::collections::{HashMap, HashSet};
+use quizx::graph::*;
+use quizx::vec_graph::Graph;
+use serde::{Deserialize, Serialize};
+
+use crate::{edge_type_from_u8, parse_phase_str, vertex_type_from_u8};
+
+#[derive(Clone, Deserialize)]
+pub struct PatternVertex {
+    pub id: usize,
+    /// 0=Boundary, 1=Z, 2=X, 3=H; omitted matches any type.
+    #[serde(default)]
+    pub vertex_type: Option<u8>,
+    /// Exact phase as "num" or "num/denom"; omitted matches any phase.
+    #[serde(default)]
+    pub phase: Option<String>,
+    /// Boundary vertices are the "glue" connecting a match to the rest of
+    /// the diagram: they may have extra neighbors outside the pattern.
+    #[serde(default)]
+    pub boundary: bool,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct PatternEdge {
+    pub source: usize,
+    pub target: usize,
+    /// 0=Simple, 1=Hadamard.
+    pub edge_type: u8,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Pattern {
+    pub vertices: Vec<PatternVertex>,
+    pub edges: Vec<PatternEdge>,
+}
+
+/// One embedding of a `Pattern` into a host graph, mapping pattern vertex
+/// ids to host vertex ids.
+#[derive(Clone, Serialize)]
+pub struct PatternMatch {
+    pub match_id: usize,
+    pub mapping: HashMap<usize, usize>,
+}
+
+impl Pattern {
+    fn vertex(&self, id: usize) -> &PatternVertex {
+        self.vertices.iter().find(|v| v.id == id)
+            .expect("pattern edge refers to an unknown vertex id")
+    }
+
+    fn degree(&self, id: usize) -> usize {
+        self.edges.iter().filter(|e| e.source == id || e.target == id).count()
+    }
+
+    fn neighbors(&self, id: usize) -> Vec<(usize, u8)> {
+        self.edges.iter()
+            .filter_map(|e| {
+                if e.source == id {
+                    Some((e.target, e.edge_type))
+                } else if e.target == id {
+                    Some((e.source, e.edge_type))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+fn host_degree(graph: &Graph, v: usize) -> usize {
+    graph.edges().filter(|(s, t, _)| *s == v || *t == v).count()
+}
+
+fn host_edge_type(graph: &Graph, a: usize, b: usize) -> Option<EType> {
+    graph.edges()
+        .find(|(s, t, _)| (*s == a && *t == b) || (*s == b && *t == a))
+        .map(|(_, _, et)| et)
+}
+
+/// VF2-style backtracking subgraph search: extend a partial injective map,
+/// at each step picking the next unmapped pattern vertex that is adjacent
+/// to an already-mapped one, so infeasible branches are pruned early.
+pub fn find_matches(graph: &Graph, pattern: &Pattern) -> Vec<HashMap<usize, usize>> {
+    let mut order: Vec<usize> = pattern.vertices.iter().map(|v| v.id).collect();
+    order.sort_by_key(|&id| std::cmp::Reverse(pattern.degree(id)));
+
+    let mut matches = Vec::new();
+    let mut mapping = HashMap::new();
+    let mut used_hosts = HashSet::new();
+    search(graph, pattern, &order, &mut mapping, &mut used_hosts, &mut matches);
+    matches
+}
+
+fn next_candidate(order: &[usize], pattern: &Pattern, mapping: &HashMap<usize, usize>) -> Option<usize> {
+    order.iter()
+        .find(|&&id| {
+            !mapping.contains_key(&id)
+                && pattern.neighbors(id).iter().any(|(n, _)| mapping.contains_key(n))
+        })
+        .or_else(|| order.iter().find(|&&id| !mapping.contains_key(&id)))
+        .copied()
+}
+
+fn feasible(
+    graph: &Graph,
+    pattern: &Pattern,
+    pv: &PatternVertex,
+    host: usize,
+    mapping: &HashMap<usize, usize>,
+) -> bool {
+    if let Some(vt) = pv.vertex_type {
+        if graph.vertex_type(host) != vertex_type_from_u8(vt) {
+            return false;
+        }
+    }
+    if let Some(phase_str) = &pv.phase {
+        match parse_phase_str(phase_str) {
+            Ok(want) if want == graph.phase(host) => {}
+            _ => return false,
+        }
+    }
+
+    let pdeg = pattern.degree(pv.id);
+    let hdeg = host_degree(graph, host);
+    if pv.boundary {
+        if hdeg < pdeg {
+            return false;
+        }
+    } else if hdeg != pdeg {
+        return false;
+    }
+
+    pattern.neighbors(pv.id).into_iter().all(|(pn, etype)| {
+        match mapping.get(&pn) {
+            Some(&hn) => host_edge_type(graph, host, hn) == Some(edge_type_from_u8(etype)),
+            None => true,
+        }
+    })
+}
+
+fn search(
+    graph: &Graph,
+    pattern: &Pattern,
+    order: &[usize],
+    mapping: &mut HashMap<usize, usize>,
+    used_hosts: &mut HashSet<usize>,
+    matches: &mut Vec<HashMap<usize, usize>>,
+) {
+    if mapping.len() == pattern.vertices.len() {
+        matches.push(mapping.clone());
+        return;
+    }
+
+    let pid = match next_candidate(order, pattern, mapping) {
+        Some(id) => id,
+        None => return,
+    };
+    let pv = pattern.vertex(pid);
+
+    for host in graph.vertices() {
+        if used_hosts.contains(&host) || !feasible(graph, pattern, pv, host, mapping) {
+            continue;
+        }
+
+        mapping.insert(pid, host);
+        used_hosts.insert(host);
+        search(graph, pattern, order, mapping, used_hosts, matches);
+        used_hosts.remove(&host);
+        mapping.remove(&pid);
+    }
+}
+
+/// Splice `replacement` into `graph` at a match found by `find_matches`:
+/// delete the matched interior vertices, instantiate the replacement's
+/// interior vertices with fresh ids, and reconnect the replacement's
+/// boundaries to the host neighbors the pattern's boundaries had mapped to.
+///
+/// The whole replacement is validated -- mapping completeness, phase syntax,
+/// edge references -- before `graph` is touched, so a malformed replacement
+/// (fully user-controlled via `apply_rewrite_json`) returns an `Err` without
+/// leaving `graph` half-rewritten.
+pub fn apply_rewrite(
+    graph: &mut Graph,
+    pattern: &Pattern,
+    replacement: &Pattern,
+    mapping: &HashMap<usize, usize>,
+) -> Result<(), String> {
+    let pattern_boundaries: HashSet<usize> = pattern.vertices.iter()
+        .filter(|v| v.boundary).map(|v| v.id).collect();
+    let replacement_boundaries: HashSet<usize> = replacement.vertices.iter()
+        .filter(|v| v.boundary).map(|v| v.id).collect();
+    if pattern_boundaries != replacement_boundaries {
+        return Err("replacement boundary ids must match the pattern's boundary ids".to_string());
+    }
+
+    // Host ids shared between the match and the replacement, keyed by the
+    // boundary id they both use as glue.
+    let mut host_of: HashMap<usize, usize> = HashMap::new();
+    for &id in &pattern_boundaries {
+        let host = *mapping.get(&id).ok_or("incomplete match mapping")?;
+        host_of.insert(id, host);
+    }
+
+    // Resolve every interior vertex to delete before touching `graph`, so an
+    // incomplete mapping can't leave the match half-deleted.
+    let mut to_remove = Vec::new();
+    for v in &pattern.vertices {
+        if v.boundary {
+            continue;
+        }
+        to_remove.push(*mapping.get(&v.id).ok_or("incomplete match mapping")?);
+    }
+
+    // Parse every replacement phase and confirm every replacement edge
+    // refers to a vertex the replacement actually declares, before
+    // instantiating anything.
+    let mut replacement_phases: HashMap<usize, num::rational::Rational64> = HashMap::new();
+    for v in &replacement.vertices {
+        if v.boundary {
+            continue;
+        }
+        if let Some(phase_str) = &v.phase {
+            replacement_phases.insert(v.id, parse_phase_str(phase_str)?);
+        }
+    }
+    let replacement_ids: HashSet<usize> = replacement.vertices.iter().map(|v| v.id).collect();
+    for e in &replacement.edges {
+        if !replacement_ids.contains(&e.source) || !replacement_ids.contains(&e.target) {
+            return Err("replacement edge refers to an unknown vertex".to_string());
+        }
+    }
+
+    // Everything has been validated; only now does `graph` get mutated.
+    for host in to_remove {
+        graph.remove_vertex(host);
+    }
+
+    for v in &replacement.vertices {
+        if v.boundary {
+            continue;
+        }
+        let host = graph.add_vertex(vertex_type_from_u8(v.vertex_type.unwrap_or(1)));
+        if let Some(phase) = replacement_phases.get(&v.id) {
+            graph.set_phase(host, *phase);
+        }
+        host_of.insert(v.id, host);
+    }
+
+    for e in &replacement.edges {
+        let s = host_of[&e.source];
+        let t = host_of[&e.target];
+        graph.add_edge_with_type(s, t, edge_type_from_u8(e.edge_type));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_graph() -> Graph {
+        // b0 -- z(1/2) -- x(0) -- b1
+        let mut g = Graph::new();
+        let b0 = g.add_vertex(VType::B);
+        let z = g.add_vertex(VType::Z);
+        let x = g.add_vertex(VType::X);
+        let b1 = g.add_vertex(VType::B);
+        g.set_phase(z, num::rational::Rational64::new(1, 2));
+        g.add_edge_with_type(b0, z, EType::N);
+        g.add_edge_with_type(z, x, EType::N);
+        g.add_edge_with_type(x, b1, EType::N);
+        g
+    }
+
+    fn pat_z_x() -> Pattern {
+        Pattern {
+            vertices: vec![
+                PatternVertex { id: 0, vertex_type: None, phase: None, boundary: true },
+                PatternVertex { id: 1, vertex_type: Some(1), phase: None, boundary: false },
+                PatternVertex { id: 2, vertex_type: Some(2), phase: None, boundary: false },
+                PatternVertex { id: 3, vertex_type: None, phase: None, boundary: true },
+            ],
+            edges: vec![
+                PatternEdge { source: 0, target: 1, edge_type: 0 },
+                PatternEdge { source: 1, target: 2, edge_type: 0 },
+                PatternEdge { source: 2, target: 3, edge_type: 0 },
+            ],
+        }
+    }
+
+    #[test]
+    fn finds_expected_match_and_applies_rewrite() {
+        let g = sample_graph();
+        let pat = pat_z_x();
+        let matches = find_matches(&g, &pat);
+        assert_eq!(matches.len(), 1);
+
+        let mut g2 = g.clone();
+        let replacement = Pattern {
+            vertices: vec![
+                PatternVertex { id: 0, vertex_type: None, phase: None, boundary: true },
+                PatternVertex { id: 3, vertex_type: None, phase: None, boundary: true },
+            ],
+            edges: vec![PatternEdge { source: 0, target: 3, edge_type: 1 }],
+        };
+        apply_rewrite(&mut g2, &pat, &replacement, &matches[0]).expect("rewrite should succeed");
+
+        assert_eq!(g2.num_vertices(), 2);
+        assert_eq!(g2.num_edges(), 1);
+    }
+
+    #[test]
+    fn rejects_malformed_replacement_without_touching_the_graph() {
+        let g = sample_graph();
+        let pat = pat_z_x();
+        let matches = find_matches(&g, &pat);
+        assert_eq!(matches.len(), 1);
+
+        let mut g2 = g.clone();
+        let before_vertices = g2.num_vertices();
+        let before_edges = g2.num_edges();
+
+        // An interior replacement vertex with an unparseable phase; the
+        // failure must surface before any vertex is removed or added.
+        let bad_replacement = Pattern {
+            vertices: vec![
+                PatternVertex { id: 0, vertex_type: None, phase: None, boundary: true },
+                PatternVertex { id: 3, vertex_type: None, phase: None, boundary: true },
+                PatternVertex { id: 4, vertex_type: Some(1), phase: Some("not-a-phase".to_string()), boundary: false },
+            ],
+            edges: vec![
+                PatternEdge { source: 0, target: 4, edge_type: 0 },
+                PatternEdge { source: 4, target: 3, edge_type: 0 },
+            ],
+        };
+
+        let err = apply_rewrite(&mut g2, &pat, &bad_replacement, &matches[0]);
+        assert!(err.is_err());
+        assert_eq!(g2.num_vertices(), before_vertices);
+        assert_eq!(g2.num_edges(), before_edges);
+    }
+
+    #[test]
+    fn rejects_zero_denominator_replacement_phase_without_touching_the_graph() {
+        let g = sample_graph();
+        let pat = pat_z_x();
+        let matches = find_matches(&g, &pat);
+        assert_eq!(matches.len(), 1);
+
+        let mut g2 = g.clone();
+        let before_vertices = g2.num_vertices();
+        let before_edges = g2.num_edges();
+
+        // A replacement phase of "1/0" must surface as an error, not panic
+        // (num::rational::Rational64::new panics on a zero denominator).
+        let bad_replacement = Pattern {
+            vertices: vec![
+                PatternVertex { id: 0, vertex_type: None, phase: None, boundary: true },
+                PatternVertex { id: 3, vertex_type: None, phase: None, boundary: true },
+                PatternVertex { id: 4, vertex_type: Some(1), phase: Some("1/0".to_string()), boundary: false },
+            ],
+            edges: vec![
+                PatternEdge { source: 0, target: 4, edge_type: 0 },
+                PatternEdge { source: 4, target: 3, edge_type: 0 },
+            ],
+        };
+
+        let err = apply_rewrite(&mut g2, &pat, &bad_replacement, &matches[0]);
+        assert!(err.is_err());
+        assert_eq!(g2.num_vertices(), before_vertices);
+        assert_eq!(g2.num_edges(), before_edges);
+    }
+
+    #[test]
+    fn rejects_dangling_replacement_edge_without_touching_the_graph() {
+        let g = sample_graph();
+        let pat = pat_z_x();
+        let matches = find_matches(&g, &pat);
+
+        let mut g2 = g.clone();
+        let before_vertices = g2.num_vertices();
+        let before_edges = g2.num_edges();
+
+        let dangling_replacement = Pattern {
+            vertices: vec![
+                PatternVertex { id: 0, vertex_type: None, phase: None, boundary: true },
+                PatternVertex { id: 3, vertex_type: None, phase: None, boundary: true },
+            ],
+            // Vertex id 99 is never declared.
+            edges: vec![PatternEdge { source: 0, target: 99, edge_type: 0 }],
+        };
+
+        let err = apply_rewrite(&mut g2, &pat, &dangling_replacement, &matches[0]);
+        assert!(err.is_err());
+        assert_eq!(g2.num_vertices(), before_vertices);
+        assert_eq!(g2.num_edges(), before_edges);
+    }
+}
@@ -0,0 +1,485 @@
+//! OpenQASM 2.0 import and circuit-extraction export.
+//!
+//! Import parses a practical subset of OpenQASM 2.0 (qreg/creg
+//! declarations and the gates h, x, z, rx, rz, cx, cz, s, sdg, t, tdg) and
+//! builds the corresponding spiders directly, one gate at a time, using the
+//! standard ZX-calculus gadgets: a single-qubit Z/X rotation becomes a
+//! Z/X spider of that phase spliced into the wire, `cx` becomes a
+//! Z (control) / X (target) pair joined by a plain wire, and `cz` a pair of
+//! Z spiders joined by a Hadamard edge. Export runs quizx's circuit-extraction
+//! path on the (already-simplified) graph and renders the resulting gate
+//! list back out as OpenQASM text.
+
+use num::rational::Rational64;
+use quizx::extract::ToCircuit;
+use quizx::graph::*;
+use quizx::vec_graph::Graph;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Str(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Semicolon,
+    Op(char),
+}
+
+fn lex(src: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = src.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            ' ' | '\t' | '\r' | '\n' => { chars.next(); }
+            '/' => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    while let Some(&c2) = chars.peek() {
+                        if c2 == '\n' { break; }
+                        chars.next();
+                    }
+                } else {
+                    tokens.push(Token::Op('/'));
+                }
+            }
+            '(' => { chars.next(); tokens.push(Token::LParen); }
+            ')' => { chars.next(); tokens.push(Token::RParen); }
+            '[' => { chars.next(); tokens.push(Token::LBracket); }
+            ']' => { chars.next(); tokens.push(Token::RBracket); }
+            ',' => { chars.next(); tokens.push(Token::Comma); }
+            ';' => { chars.next(); tokens.push(Token::Semicolon); }
+            '+' | '-' | '*' => { chars.next(); tokens.push(Token::Op(c)); }
+            '"' => {
+                chars.next();
+                let mut s = String::new();
+                for c2 in chars.by_ref() {
+                    if c2 == '"' { break; }
+                    s.push(c2);
+                }
+                tokens.push(Token::Str(s));
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut n = String::new();
+                while let Some(&c2) = chars.peek() {
+                    if c2.is_ascii_digit() || c2 == '.' {
+                        n.push(c2);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Number(n));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut id = String::new();
+                while let Some(&c2) = chars.peek() {
+                    if c2.is_alphanumeric() || c2 == '_' {
+                        id.push(c2);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(id));
+            }
+            _ => return Err(format!("unexpected character '{}' in QASM source", c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Evaluate a phase expression of the form seen in `rx(pi/2)`, `rz(3*pi/4)`,
+/// `rz(-pi)`: a run of numbers and exactly one `pi`, combined with `*`/`/`
+/// and an optional leading `-`. Anything that isn't a rational multiple of
+/// pi (e.g. a decimal literal) is rejected rather than rounded.
+fn eval_phase_expr(tokens: &[Token]) -> Result<Rational64, String> {
+    let mut idx = 0;
+    let negate = if tokens.first() == Some(&Token::Op('-')) {
+        idx += 1;
+        true
+    } else {
+        false
+    };
+
+    let (mut coeff, mut pi_count) = read_phase_factor(tokens, &mut idx)?;
+    while idx < tokens.len() {
+        match &tokens[idx] {
+            Token::Op('*') => {
+                idx += 1;
+                let (c, p) = read_phase_factor(tokens, &mut idx)?;
+                coeff *= c;
+                pi_count += p;
+            }
+            Token::Op('/') => {
+                idx += 1;
+                let (c, p) = read_phase_factor(tokens, &mut idx)?;
+                if *c.numer() == 0 {
+                    return Err("division by zero in phase expression".to_string());
+                }
+                coeff /= c;
+                pi_count -= p;
+            }
+            other => return Err(format!("unexpected token {:?} in phase expression", other)),
+        }
+    }
+
+    if pi_count != 1 {
+        return Err("phase expression is not a rational multiple of pi".to_string());
+    }
+    Ok(if negate { -coeff } else { coeff })
+}
+
+fn read_phase_factor(tokens: &[Token], idx: &mut usize) -> Result<(Rational64, i32), String> {
+    match tokens.get(*idx) {
+        Some(Token::Ident(id)) if id == "pi" => {
+            *idx += 1;
+            Ok((Rational64::new(1, 1), 1))
+        }
+        Some(Token::Number(n)) => {
+            if n.contains('.') {
+                return Err(format!("decimal phase literal '{}' is not a rational multiple of pi", n));
+            }
+            *idx += 1;
+            let v: i64 = n.parse().map_err(|_| format!("invalid number '{}'", n))?;
+            Ok((Rational64::new(v, 1), 0))
+        }
+        other => Err(format!("expected a number or 'pi' in phase expression, got {:?}", other)),
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        Parser { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let t = self.tokens.get(self.pos);
+        self.pos += 1;
+        t
+    }
+
+    fn expect(&mut self, want: &Token) -> Result<(), String> {
+        match self.next() {
+            Some(t) if t == want => Ok(()),
+            other => Err(format!("expected {:?}, got {:?}", want, other)),
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Ident(s)) => Ok(s.clone()),
+            other => Err(format!("expected an identifier, got {:?}", other)),
+        }
+    }
+
+    fn expect_number(&mut self) -> Result<u64, String> {
+        match self.next() {
+            Some(Token::Number(n)) => n.parse().map_err(|_| format!("invalid integer '{}'", n)),
+            other => Err(format!("expected a number, got {:?}", other)),
+        }
+    }
+
+    /// `OPENQASM`'s `<major>.<minor>` version number (e.g. "2.0"), which the
+    /// lexer hands back as a single `Number` token containing a `.`. The
+    /// version isn't otherwise used, so only its shape is checked.
+    fn expect_version(&mut self) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Number(n)) => n.parse::<f64>()
+                .map(|_| ())
+                .map_err(|_| format!("invalid version number '{}'", n)),
+            other => Err(format!("expected a version number, got {:?}", other)),
+        }
+    }
+
+    /// `ident[index]`, the only qubit/bit reference form this subset supports.
+    fn expect_qubit_ref(&mut self) -> Result<(String, u64), String> {
+        let reg = self.expect_ident()?;
+        self.expect(&Token::LBracket)?;
+        let index = self.expect_number()?;
+        self.expect(&Token::RBracket)?;
+        Ok((reg, index))
+    }
+
+    /// A parenthesized, comma-separated list of phase expressions, e.g. the
+    /// `(pi/2)` in `rx(pi/2) q[0];`. Empty if the gate takes no parameters.
+    fn parse_params(&mut self) -> Result<Vec<Rational64>, String> {
+        if self.peek() != Some(&Token::LParen) {
+            return Ok(Vec::new());
+        }
+        self.next();
+        let mut params = Vec::new();
+        loop {
+            let start = self.pos;
+            while !matches!(self.peek(), Some(Token::Comma) | Some(Token::RParen) | None) {
+                self.next();
+            }
+            params.push(eval_phase_expr(&self.tokens[start..self.pos])?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::RParen) => break,
+                other => return Err(format!("expected ',' or ')', got {:?}", other)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// A ZX-diagram under construction from a QASM program: each declared qubit
+/// has a running "frontier" vertex that the next gate on that wire attaches
+/// to, and a column coordinate used as its `qubit`/row position.
+struct Builder {
+    graph: Graph,
+    columns: std::collections::HashMap<(String, u64), usize>,
+    frontier: Vec<usize>,
+    row: f64,
+}
+
+impl Builder {
+    fn new() -> Self {
+        Builder {
+            graph: Graph::new(),
+            columns: std::collections::HashMap::new(),
+            frontier: Vec::new(),
+            row: 1.0,
+        }
+    }
+
+    fn declare_qreg(&mut self, name: &str, size: u64) {
+        for i in 0..size {
+            let col = self.frontier.len();
+            self.columns.insert((name.to_string(), i), col);
+
+            let v = self.graph.add_vertex(VType::B);
+            self.graph.set_row(v, 0.0);
+            self.graph.set_qubit(v, col as f64);
+            self.frontier.push(v);
+        }
+    }
+
+    fn column(&self, qref: &(String, u64)) -> Result<usize, String> {
+        self.columns.get(qref).copied()
+            .ok_or_else(|| format!("qubit '{}[{}]' was never declared with qreg", qref.0, qref.1))
+    }
+
+    fn append_single(&mut self, col: usize, vt: VType, phase: Rational64) {
+        let v = self.graph.add_vertex(vt);
+        self.graph.set_row(v, self.row);
+        self.graph.set_qubit(v, col as f64);
+        self.graph.set_phase(v, phase);
+        self.graph.add_edge_with_type(self.frontier[col], v, EType::N);
+        self.frontier[col] = v;
+        self.row += 1.0;
+    }
+
+    fn append_h(&mut self, col: usize) {
+        let v = self.graph.add_vertex(VType::H);
+        self.graph.set_row(v, self.row);
+        self.graph.set_qubit(v, col as f64);
+        self.graph.add_edge_with_type(self.frontier[col], v, EType::N);
+        self.frontier[col] = v;
+        self.row += 1.0;
+    }
+
+    fn append_controlled(&mut self, ctrl: usize, target: usize, target_vtype: VType, link: EType) {
+        let c = self.graph.add_vertex(VType::Z);
+        let t = self.graph.add_vertex(target_vtype);
+        self.graph.set_row(c, self.row);
+        self.graph.set_qubit(c, ctrl as f64);
+        self.graph.set_row(t, self.row);
+        self.graph.set_qubit(t, target as f64);
+
+        self.graph.add_edge_with_type(self.frontier[ctrl], c, EType::N);
+        self.graph.add_edge_with_type(self.frontier[target], t, EType::N);
+        self.graph.add_edge_with_type(c, t, link);
+
+        self.frontier[ctrl] = c;
+        self.frontier[target] = t;
+        self.row += 1.0;
+    }
+
+    /// Close every wire with an output boundary vertex.
+    fn finish(mut self) -> Graph {
+        for col in 0..self.frontier.len() {
+            let v = self.graph.add_vertex(VType::B);
+            self.graph.set_row(v, self.row);
+            self.graph.set_qubit(v, col as f64);
+            self.graph.add_edge_with_type(self.frontier[col], v, EType::N);
+        }
+        self.graph
+    }
+}
+
+fn parse_gate(parser: &mut Parser, name: &str, builder: &mut Builder) -> Result<(), String> {
+    match name {
+        "h" => {
+            let q = parser.expect_qubit_ref()?;
+            let col = builder.column(&q)?;
+            builder.append_h(col);
+        }
+        "x" | "z" | "s" | "sdg" | "t" | "tdg" => {
+            let q = parser.expect_qubit_ref()?;
+            let col = builder.column(&q)?;
+            let (vt, phase) = match name {
+                "x" => (VType::X, Rational64::new(1, 1)),
+                "z" => (VType::Z, Rational64::new(1, 1)),
+                "s" => (VType::Z, Rational64::new(1, 2)),
+                "sdg" => (VType::Z, Rational64::new(-1, 2)),
+                "t" => (VType::Z, Rational64::new(1, 4)),
+                _ => (VType::Z, Rational64::new(-1, 4)), // tdg
+            };
+            builder.append_single(col, vt, phase);
+        }
+        "rx" | "rz" => {
+            let params = parser.parse_params()?;
+            let phase = *params.first().ok_or("rx/rz requires a phase argument")?;
+            let q = parser.expect_qubit_ref()?;
+            let col = builder.column(&q)?;
+            let vt = if name == "rx" { VType::X } else { VType::Z };
+            builder.append_single(col, vt, phase);
+        }
+        "cx" | "cz" => {
+            let ctrl_ref = parser.expect_qubit_ref()?;
+            parser.expect(&Token::Comma)?;
+            let target_ref = parser.expect_qubit_ref()?;
+            let ctrl = builder.column(&ctrl_ref)?;
+            let target = builder.column(&target_ref)?;
+            if name == "cx" {
+                builder.append_controlled(ctrl, target, VType::X, EType::N);
+            } else {
+                builder.append_controlled(ctrl, target, VType::Z, EType::H);
+            }
+        }
+        _ => return Err(format!("unsupported gate '{}'", name)),
+    }
+    Ok(())
+}
+
+/// Parse a practical OpenQASM 2.0 subset and build the corresponding
+/// ZX-diagram. Unrecognized gates and non-rational-multiple-of-pi phase
+/// arguments are rejected rather than approximated.
+pub fn parse(src: &str) -> Result<Graph, String> {
+    let tokens = lex(src)?;
+    let mut parser = Parser::new(&tokens);
+    let mut builder = Builder::new();
+
+    while parser.peek().is_some() {
+        let head = parser.expect_ident()?;
+        match head.as_str() {
+            "OPENQASM" => {
+                parser.expect_version()?;
+                parser.expect(&Token::Semicolon)?;
+            }
+            "include" => {
+                match parser.next() {
+                    Some(Token::Str(_)) => {}
+                    other => return Err(format!("expected a string after 'include', got {:?}", other)),
+                }
+                parser.expect(&Token::Semicolon)?;
+            }
+            "qreg" => {
+                let name = parser.expect_ident()?;
+                parser.expect(&Token::LBracket)?;
+                let size = parser.expect_number()?;
+                parser.expect(&Token::RBracket)?;
+                parser.expect(&Token::Semicolon)?;
+                builder.declare_qreg(&name, size);
+            }
+            "creg" => {
+                // Classical registers don't appear in a ZX-diagram built
+                // from this gate subset (no measurements); parse and drop.
+                parser.expect_ident()?;
+                parser.expect(&Token::LBracket)?;
+                parser.expect_number()?;
+                parser.expect(&Token::RBracket)?;
+                parser.expect(&Token::Semicolon)?;
+            }
+            gate => {
+                parse_gate(&mut parser, gate, &mut builder)?;
+                parser.expect(&Token::Semicolon)?;
+            }
+        }
+    }
+
+    // The graph's scalar is left at its initial value, so the diagram equals
+    // the circuit only up to a nonzero scalar: each `cx`/`cz` gadget is its
+    // gate matrix divided by sqrt(2), and an `rx` spider differs from the
+    // gate by a global phase, and neither factor is folded into the scalar.
+    Ok(builder.finish())
+}
+
+/// Run quizx's circuit-extraction path on `graph` and render the result as
+/// OpenQASM text. Fails with a descriptive error if the diagram isn't in an
+/// extractable (graph-like) form.
+pub fn export(graph: &Graph) -> Result<String, String> {
+    let circuit = graph.clone().to_circuit()
+        .map_err(|e| format!("Diagram is not in an extractable form: {}", e))?;
+    Ok(circuit.to_qasm())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_standard_qasm_header_and_basic_gates() {
+        let src = r#"
+            OPENQASM 2.0;
+            include "qelib1.inc";
+            qreg q[2];
+            creg c[2];
+            h q[0];
+            cx q[0],q[1];
+        "#;
+
+        let g = parse(src).expect("standard OPENQASM 2.0 header should parse");
+        // 2 input boundaries + 1 h spider + 2 cx spiders + 2 output boundaries.
+        assert_eq!(g.num_vertices(), 7);
+        assert_eq!(g.num_edges(), 6);
+    }
+
+    #[test]
+    fn rejects_decimal_phase_argument() {
+        let src = "OPENQASM 2.0;\nqreg q[1];\nrz(0.5) q[0];\n";
+        assert!(parse(src).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_gate() {
+        let src = "OPENQASM 2.0;\nqreg q[1];\nbogus q[0];\n";
+        assert!(parse(src).is_err());
+    }
+
+    #[test]
+    fn exports_a_simple_circuit_back_to_qasm() {
+        let src = "OPENQASM 2.0;\nqreg q[2];\nh q[0];\ncx q[0],q[1];\n";
+        let g = parse(src).expect("source should parse");
+
+        let qasm = export(&g).expect("a freshly parsed circuit diagram should be extractable");
+        assert!(qasm.to_uppercase().contains("OPENQASM"));
+    }
+
+    #[test]
+    fn rejects_a_non_extractable_diagram() {
+        // A boundary vertex with no edge at all isn't a valid circuit wire
+        // endpoint, so this diagram can't be extracted back to a circuit.
+        let mut g = Graph::new();
+        g.add_vertex(VType::B);
+
+        assert!(export(&g).is_err());
+    }
+}